@@ -0,0 +1,22 @@
+use tauri::{AppHandle, State};
+
+use crate::ssh::agent::{self, SshAgentState};
+
+/// Starts the embedded ssh-agent and returns the socket path it is listening on.
+#[tauri::command]
+pub fn start_agent(app_handle: AppHandle, state: State<SshAgentState>) -> Result<String, String> {
+    agent::start(&app_handle, &state)
+}
+
+#[tauri::command]
+pub fn stop_agent(state: State<SshAgentState>) -> Result<(), String> {
+    agent::stop(&state)
+}
+
+/// Caches a key's passphrase in memory so the agent can use it to answer
+/// sign requests without prompting again until the agent is stopped.
+#[tauri::command]
+pub fn unlock_key(key_id: i64, passphrase: String, state: State<SshAgentState>) -> Result<(), String> {
+    agent::unlock_key(&state, key_id, passphrase);
+    Ok(())
+}
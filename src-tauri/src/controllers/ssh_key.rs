@@ -1,28 +1,25 @@
 use tauri::AppHandle;
 use std::fs;
+use std::path::Path;
 use rusqlite::params;
+use ssh_key::private::{Ed25519Keypair, EcdsaKeypair, KeypairData, RsaKeypair};
+use ssh_key::rand_core::OsRng;
+use ssh_key::{EcdsaCurve, HashAlg, LineEnding, PrivateKey};
 use crate::database::connection::get;
 use crate::models::SshKey;
 
 #[tauri::command]
-pub fn add_ssh_key(app_handle: AppHandle, name: String, path: String, is_default: bool) -> Result<i64, String> {
+pub fn add_ssh_key(app_handle: AppHandle, name: String, path: String, is_default: bool, fingerprint: Option<String>) -> Result<i64, String> {
     let conn = get(&app_handle)?;
 
     // Use current time for timestamps
     let now = chrono::Local::now().to_rfc3339();
 
-    // If this key is default, unset any existing default
-    if is_default {
-        conn.execute(
-            "UPDATE ssh_keys SET is_default = 0 WHERE is_default = 1",
-            [],
-        ).map_err(|e| e.to_string())?;
-    }
-
+    // trg_ssh_keys_single_default_insert clears any previous default for us.
     conn.execute(
-        "INSERT INTO ssh_keys (name, path, is_default, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![name, path, is_default, now, now],
+        "INSERT INTO ssh_keys (name, path, is_default, fingerprint, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![name, path, is_default, fingerprint, now, now],
     ).map_err(|e| e.to_string())?;
 
     Ok(conn.last_insert_rowid())
@@ -33,7 +30,7 @@ pub fn get_ssh_key(app_handle: AppHandle, id: i64) -> Result<SshKey, String> {
     let conn = get(&app_handle)?;
 
     conn.query_row(
-        "SELECT id, name, path, is_default, created_at, updated_at
+        "SELECT id, name, path, is_default, fingerprint, created_at, updated_at
          FROM ssh_keys WHERE id = ?1",
         params![id],
         |row| Ok(SshKey {
@@ -41,8 +38,9 @@ pub fn get_ssh_key(app_handle: AppHandle, id: i64) -> Result<SshKey, String> {
             name: row.get(1)?,
             path: row.get(2)?,
             is_default: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
+            fingerprint: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
         })
     ).map_err(|e| e.to_string())
 }
@@ -52,7 +50,7 @@ pub fn get_ssh_keys(app_handle: AppHandle) -> Result<Vec<SshKey>, String> {
     let conn = get(&app_handle)?;
 
     let mut stmt = conn.prepare("
-        SELECT id, name, path, is_default, created_at, updated_at
+        SELECT id, name, path, is_default, fingerprint, created_at, updated_at
         FROM ssh_keys
     ").map_err(|e| e.to_string())?;
 
@@ -62,8 +60,9 @@ pub fn get_ssh_keys(app_handle: AppHandle) -> Result<Vec<SshKey>, String> {
             name: row.get(1)?,
             path: row.get(2)?,
             is_default: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
+            fingerprint: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
         })
     }).map_err(|e| e.to_string())?;
 
@@ -79,13 +78,7 @@ pub fn get_ssh_keys(app_handle: AppHandle) -> Result<Vec<SshKey>, String> {
 pub fn set_default_ssh_key(app_handle: AppHandle, id: i64) -> Result<(), String> {
     let conn = get(&app_handle)?;
 
-    // First, unset any existing default
-    conn.execute(
-        "UPDATE ssh_keys SET is_default = 0 WHERE is_default = 1",
-        [],
-    ).map_err(|e| e.to_string())?;
-
-    // Then set the new default
+    // trg_ssh_keys_single_default_update clears any previous default for us.
     conn.execute(
         "UPDATE ssh_keys SET is_default = 1 WHERE id = ?1",
         params![id],
@@ -130,13 +123,41 @@ pub fn delete_ssh_key(app_handle: AppHandle, id: i64, delete_file: bool) -> Resu
     Ok(())
 }
 
+/// Builds the `KeypairData` for a freshly generated key of the requested algorithm.
+///
+/// `bits` only applies to `rsa` (default 3072) and is ignored otherwise.
+fn generate_keypair(algorithm: &str, bits: Option<u32>) -> Result<KeypairData, String> {
+    match algorithm {
+        "ed25519" => Ok(KeypairData::Ed25519(Ed25519Keypair::random(&mut OsRng))),
+        "rsa" => {
+            let bits = bits.unwrap_or(3072) as usize;
+            let keypair = RsaKeypair::random(&mut OsRng, bits)
+                .map_err(|e| format!("Failed to generate RSA key: {}", e))?;
+            Ok(KeypairData::Rsa(keypair))
+        }
+        "ecdsa" => {
+            let keypair = EcdsaKeypair::random(&mut OsRng, EcdsaCurve::NistP256)
+                .map_err(|e| format!("Failed to generate ECDSA key: {}", e))?;
+            Ok(KeypairData::Ecdsa(keypair))
+        }
+        other => Err(format!("Unsupported key algorithm: {}", other)),
+    }
+}
+
 #[tauri::command]
-pub fn generate_ssh_key(app_handle: AppHandle, name: String) -> Result<String, String> {
-    // Get the user's home directory
-    let home_dir = dirs::home_dir().ok_or("Could not get home directory")?;
-    let ssh_dir = home_dir.join(".ssh");
+pub fn generate_ssh_key(
+    app_handle: AppHandle,
+    name: String,
+    algorithm: String,
+    bits: Option<u32>,
+    passphrase: Option<String>,
+    overwrite: bool,
+) -> Result<String, String> {
+    let conn = get(&app_handle)?;
+    let ssh_dir = crate::ssh::ssh_dir(&conn)?;
+    drop(conn);
 
-    // Create .ssh directory if it doesn't exist
+    // Create the ssh directory if it doesn't exist
     if !ssh_dir.exists() {
         fs::create_dir_all(&ssh_dir).map_err(|e| e.to_string())?;
         // Set appropriate permissions (unix only)
@@ -148,28 +169,321 @@ pub fn generate_ssh_key(app_handle: AppHandle, name: String) -> Result<String, S
         }
     }
 
-    let key_path = ssh_dir.join(format!("{}", name));
-    let key_path_str = key_path.to_str().ok_or("Invalid path")?.to_string();
-
-    // Generate key using ssh-keygen via Command
-    use std::process::Command;
+    let key_path = ssh_dir.join(&name);
+    let pub_key_path = ssh_dir.join(format!("{}.pub", name));
+    if !overwrite && (key_path.exists() || pub_key_path.exists()) {
+        return Err(format!("Key '{}' already exists at {}", name, key_path.display()));
+    }
 
-    let output = Command::new("ssh-keygen")
-        .arg("-t")
-        .arg("ed25519")
-        .arg("-f")
-        .arg(&key_path)
-        .arg("-N")  // Empty passphrase
-        .arg("")
-        .output()
-        .map_err(|e| format!("Failed to execute ssh-keygen: {}", e))?;
+    let keypair = generate_keypair(&algorithm, bits)?;
+    let mut private_key = PrivateKey::new(keypair, name.clone())
+        .map_err(|e| format!("Failed to build private key: {}", e))?;
 
-    if !output.status.success() {
-        return Err(format!("ssh-keygen failed: {}", String::from_utf8_lossy(&output.stderr)));
+    if let Some(ref passphrase) = passphrase {
+        private_key = private_key
+            .encrypt(&mut OsRng, passphrase)
+            .map_err(|e| format!("Failed to encrypt private key: {}", e))?;
     }
 
+    let fingerprint = private_key.public_key().fingerprint(HashAlg::Sha256).to_string();
+
+    write_key_file(&key_path, &private_key.to_openssh(LineEnding::LF).map_err(|e| e.to_string())?, 0o600)?;
+    write_key_file(&pub_key_path, &private_key.public_key().to_openssh().map_err(|e| e.to_string())?, 0o644)?;
+
+    let key_path_str = key_path.to_str().ok_or("Invalid path")?.to_string();
+
     // Add to database
-    add_ssh_key(app_handle, name.clone(), key_path_str.clone(), false)?;
+    add_ssh_key(app_handle, name, key_path_str.clone(), false, Some(fingerprint))?;
 
     Ok(key_path_str)
-}
\ No newline at end of file
+}
+
+fn write_key_file(path: &Path, contents: &str, #[cfg_attr(not(unix), allow(unused_variables))] mode: u32) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| e.to_string())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Outcome of deploying a public key to a remote host's `authorized_keys`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeployStatus {
+    Added,
+    AlreadyPresent,
+}
+
+/// Escapes a string for safe embedding inside single-quoted shell text.
+fn shell_single_quote(value: &str) -> String {
+    value.replace('\'', "'\\''")
+}
+
+#[tauri::command]
+pub fn deploy_public_key(
+    app_handle: AppHandle,
+    key_id: i64,
+    server_id: i64,
+    passphrase: Option<String>,
+    bootstrap_password: Option<String>,
+) -> Result<DeployStatus, String> {
+    let conn = get(&app_handle)?;
+
+    let key_path: String = conn
+        .query_row(
+            "SELECT path FROM ssh_keys WHERE id = ?1",
+            params![key_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let public_key = fs::read_to_string(format!("{}.pub", key_path))
+        .map_err(|e| format!("Failed to read public key: {}", e))?;
+    let public_key = shell_single_quote(public_key.trim());
+
+    // A host with no stored key deployed to it yet has nothing for
+    // `crate::ssh::connect`'s publickey-only pool to authenticate with, so
+    // bootstrapping a brand-new host goes over a one-off password session
+    // instead of the pooled, key-authenticated path.
+    let bootstrap_target = match bootstrap_password {
+        Some(ref password) => {
+            let (host, port, username): (String, u16, String) = conn
+                .query_row(
+                    "SELECT host, port, username FROM servers WHERE id = ?1",
+                    params![server_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .map_err(|e| e.to_string())?;
+            Some((host, port, username, password.clone()))
+        }
+        None => None,
+    };
+
+    let run_remote = |command: String| -> Result<crate::ssh::CommandOutput, String> {
+        match bootstrap_target {
+            Some((ref host, port, ref username, ref password)) => {
+                crate::ssh::run_with_password(host, port, username, password, &command)
+            }
+            None => crate::ssh::run(&app_handle, server_id, command),
+        }
+    };
+
+    if bootstrap_target.is_none() {
+        crate::ssh::connect(&app_handle, server_id, passphrase)?;
+    }
+
+    let setup_and_check = run_remote(format!(
+        "mkdir -p ~/.ssh && chmod 700 ~/.ssh && touch ~/.ssh/authorized_keys && chmod 600 ~/.ssh/authorized_keys && grep -qxF '{}' ~/.ssh/authorized_keys",
+        public_key
+    ))?;
+
+    let status = if setup_and_check.exit_code == 0 {
+        DeployStatus::AlreadyPresent
+    } else {
+        run_remote(format!("echo '{}' >> ~/.ssh/authorized_keys", public_key))?;
+        DeployStatus::Added
+    };
+
+    record_deployment(&conn, key_id, server_id)?;
+
+    Ok(status)
+}
+
+fn record_deployment(conn: &rusqlite::Connection, key_id: i64, server_id: i64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO key_deployments (key_id, server_id, deployed_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(key_id, server_id) DO UPDATE SET deployed_at = excluded.deployed_at",
+        params![key_id, server_id, chrono::Local::now().to_rfc3339()],
+    ).map_err(|e| e.to_string())
+}
+
+/// Outcome of rotating one server's `authorized_keys` during a key renewal.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RenewalResult {
+    pub server_id: i64,
+    pub success: bool,
+    pub message: Option<String>,
+}
+
+#[tauri::command]
+pub fn renew_key(app_handle: AppHandle, key_id: i64, passphrase: Option<String>) -> Result<Vec<RenewalResult>, String> {
+    let conn = get(&app_handle)?;
+
+    let (name, path): (String, String) = conn
+        .query_row(
+            "SELECT name, path FROM ssh_keys WHERE id = ?1",
+            params![key_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let old_private_pem = fs::read_to_string(&path).map_err(|e| format!("Failed to read existing key: {}", e))?;
+    let old_private_key: PrivateKey = old_private_pem.parse().map_err(|e: ssh_key::Error| e.to_string())?;
+    let old_public = fs::read_to_string(format!("{}.pub", path)).map_err(|e| e.to_string())?;
+    let old_public = shell_single_quote(old_public.trim());
+
+    let algorithm = match old_private_key.algorithm() {
+        ssh_key::Algorithm::Ed25519 => "ed25519",
+        ssh_key::Algorithm::Rsa { .. } => "rsa",
+        ssh_key::Algorithm::Ecdsa { .. } => "ecdsa",
+        other => return Err(format!("Cannot renew unsupported key algorithm: {:?}", other)),
+    };
+
+    let keypair = generate_keypair(algorithm, None)?;
+    let mut new_private_key = PrivateKey::new(keypair, name)
+        .map_err(|e| format!("Failed to build private key: {}", e))?;
+    if let Some(ref passphrase) = passphrase {
+        new_private_key = new_private_key
+            .encrypt(&mut OsRng, passphrase)
+            .map_err(|e| format!("Failed to encrypt private key: {}", e))?;
+    }
+
+    let new_fingerprint = new_private_key.public_key().fingerprint(HashAlg::Sha256).to_string();
+    let new_private_openssh = new_private_key.to_openssh(LineEnding::LF).map_err(|e| e.to_string())?;
+    let new_public_openssh = new_private_key.public_key().to_openssh().map_err(|e| e.to_string())?;
+    let new_public = shell_single_quote(new_public_openssh.trim());
+
+    let server_ids: Vec<i64> = {
+        let mut stmt = conn
+            .prepare("SELECT server_id FROM key_deployments WHERE key_id = ?1")
+            .map_err(|e| e.to_string())?;
+        stmt.query_map(params![key_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    // Roll out to every host before touching the local key files, so a
+    // mid-rotation failure leaves the old key still valid everywhere rather
+    // than locking the user out of hosts that haven't been updated yet.
+    let mut results = Vec::with_capacity(server_ids.len());
+    let mut rotated_servers = Vec::new();
+    for server_id in server_ids {
+        let outcome = rotate_authorized_keys(&app_handle, server_id, &old_public, &new_public, passphrase.clone());
+        results.push(match outcome {
+            Ok(()) => {
+                rotated_servers.push(server_id);
+                RenewalResult { server_id, success: true, message: None }
+            }
+            Err(e) => RenewalResult { server_id, success: false, message: Some(e) },
+        });
+    }
+
+    if results.iter().all(|r| r.success) {
+        write_key_file(Path::new(&path), &new_private_openssh, 0o600)?;
+        write_key_file(Path::new(&format!("{}.pub", path)), &new_public_openssh, 0o644)?;
+
+        let now = chrono::Local::now().to_rfc3339();
+        conn.execute(
+            "UPDATE ssh_keys SET fingerprint = ?1, updated_at = ?2 WHERE id = ?3",
+            params![new_fingerprint, now, key_id],
+        ).map_err(|e| e.to_string())?;
+    } else {
+        // At least one host still only trusts the old key, so the old private
+        // key is the only copy that can reach it — keep it on disk untouched
+        // and walk back any host we already rotated rather than stranding it
+        // on a new key nothing else has saved.
+        //
+        // If a rollback itself fails, that host is left trusting the new
+        // public key while the matching private key only exists in memory.
+        // Stash the new keypair next to the old one so it isn't lost, and
+        // flag the host's result as failed rather than reporting a rotation
+        // that didn't actually succeed.
+        let recovery_path = format!("{}.recovered-{}", path, key_id);
+        let mut recovery_saved = false;
+        for server_id in rotated_servers {
+            if let Err(e) = rotate_authorized_keys(&app_handle, server_id, &new_public, &old_public, passphrase.clone()) {
+                if !recovery_saved {
+                    if write_key_file(Path::new(&recovery_path), &new_private_openssh, 0o600).is_ok() {
+                        let _ = write_key_file(Path::new(&format!("{}.pub", recovery_path)), &new_public_openssh, 0o644);
+                        recovery_saved = true;
+                    }
+                }
+
+                if let Some(result) = results.iter_mut().find(|r| r.server_id == server_id) {
+                    result.success = false;
+                    result.message = Some(if recovery_saved {
+                        format!(
+                            "rotated to the new key but rollback failed, host needs manual attention: {} (new private key saved to {})",
+                            e, recovery_path
+                        )
+                    } else {
+                        format!(
+                            "rotated to the new key but rollback failed, host needs manual attention: {}",
+                            e
+                        )
+                    });
+                }
+                eprintln!("failed to roll back key rotation on server {}: {}", server_id, e);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Atomically swaps the old public key line for the new one in a server's
+/// `authorized_keys`, via a temp file + rename so a dropped connection can
+/// never leave the file half-written.
+///
+/// `passphrase` unlocks the server's configured key if the connection pool
+/// isn't already warm for it — without it, rotating a passphrase-protected
+/// key fails on the very first connect of a fresh app session.
+fn rotate_authorized_keys(
+    app_handle: &AppHandle,
+    server_id: i64,
+    old_public: &str,
+    new_public: &str,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    crate::ssh::connect(app_handle, server_id, passphrase)?;
+
+    let output = crate::ssh::run(
+        app_handle,
+        server_id,
+        format!(
+            "tmp=$(mktemp ~/.ssh/authorized_keys.XXXXXX) && \
+             grep -vxF '{old}' ~/.ssh/authorized_keys > \"$tmp\"; \
+             grep -qxF '{new}' \"$tmp\" || echo '{new}' >> \"$tmp\"; \
+             chmod 600 \"$tmp\" && mv -f \"$tmp\" ~/.ssh/authorized_keys",
+            old = old_public,
+            new = new_public,
+        ),
+    )?;
+
+    if output.exit_code != 0 {
+        return Err(format!("remote update exited with {}: {}", output.exit_code, output.stderr.trim()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_single_quote_passes_through_plain_text() {
+        assert_eq!(shell_single_quote("ssh-ed25519 AAAA user@host"), "ssh-ed25519 AAAA user@host");
+    }
+
+    #[test]
+    fn shell_single_quote_escapes_embedded_quotes() {
+        assert_eq!(shell_single_quote("it's a key"), "it'\\''s a key");
+    }
+
+    #[test]
+    fn generate_keypair_ed25519() {
+        let keypair = generate_keypair("ed25519", None).unwrap();
+        assert!(matches!(keypair, KeypairData::Ed25519(_)));
+    }
+
+    #[test]
+    fn generate_keypair_rejects_unknown_algorithm() {
+        assert!(generate_keypair("dsa", None).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+use tauri::AppHandle;
+
+use crate::ssh::{self, CommandOutput};
+
+#[tauri::command]
+pub fn connect_server(app_handle: AppHandle, server_id: i64, passphrase: Option<String>) -> Result<(), String> {
+    ssh::connect(&app_handle, server_id, passphrase)
+}
+
+#[tauri::command]
+pub fn run_command(app_handle: AppHandle, server_id: i64, command: String) -> Result<CommandOutput, String> {
+    ssh::run(&app_handle, server_id, command)
+}
+
+#[tauri::command]
+pub fn disconnect_server(app_handle: AppHandle, server_id: i64) -> Result<(), String> {
+    ssh::disconnect(&app_handle, server_id)
+}
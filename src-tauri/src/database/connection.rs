@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use rusqlite::Connection;
+use tauri::{AppHandle, Manager};
+
+use super::migrations;
+
+fn db_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    Ok(data_dir.join("syndeos.db"))
+}
+
+/// Opens a connection to the app database with foreign key enforcement on.
+pub fn get(app_handle: &AppHandle) -> Result<Connection, String> {
+    let conn = Connection::open(db_path(app_handle)?).map_err(|e| e.to_string())?;
+    conn.execute("PRAGMA foreign_keys = ON", []).map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+/// Opens the database and brings its schema up to date, returning the db path.
+pub fn init_database(app_handle: AppHandle) -> Result<String, String> {
+    let conn = get(&app_handle)?;
+    migrations::run_migrations(&conn)?;
+    Ok(db_path(&app_handle)?.to_string_lossy().to_string())
+}
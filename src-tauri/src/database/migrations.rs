@@ -0,0 +1,220 @@
+use rusqlite::Connection;
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    sql: &'static str,
+}
+
+/// Ordered, embedded schema migrations. Add new ones here; never edit an
+/// already-released migration's SQL, add a new `VN__*.sql` file instead.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial",
+        sql: include_str!("migrations/V1__initial.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "ssh_key_fingerprint",
+        sql: include_str!("migrations/V2__ssh_key_fingerprint.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "key_deployments",
+        sql: include_str!("migrations/V3__key_deployments.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "servers_ssh_key_fk",
+        sql: include_str!("migrations/V4__servers_ssh_key_fk.sql"),
+    },
+];
+
+/// Applies any migration newer than the database's recorded `schema_version`,
+/// each inside its own transaction so a failure can't leave a migration
+/// half-applied and recorded as complete.
+pub fn run_migrations(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    ).map_err(|e| e.to_string())?;
+
+    let current_version: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current_version) {
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+        tx.execute_batch(migration.sql).map_err(|e| {
+            format!("migration V{} ({}) failed: {}", migration.version, migration.name, e)
+        })?;
+
+        tx.execute(
+            "INSERT INTO schema_version (version, name, applied_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![migration.version, migration.name, chrono::Local::now().to_rfc3339()],
+        ).map_err(|e| e.to_string())?;
+
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_migrations_twice_is_a_no_op() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        run_migrations(&conn).unwrap();
+        let version_after_first_run: i64 = conn
+            .query_row("SELECT COALESCE(MAX(version), 0) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version_after_first_run, MIGRATIONS.last().unwrap().version);
+
+        run_migrations(&conn).unwrap();
+        let rows_per_version: Vec<i64> = {
+            let mut stmt = conn.prepare("SELECT version FROM schema_version ORDER BY version").unwrap();
+            stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+        };
+        assert_eq!(rows_per_version, (1..=MIGRATIONS.last().unwrap().version).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn migrations_are_applied_in_version_order() {
+        let versions: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let mut sorted = versions.clone();
+        sorted.sort_unstable();
+        assert_eq!(versions, sorted, "MIGRATIONS must be declared in ascending version order");
+    }
+
+    #[test]
+    fn marking_a_key_default_clears_the_previous_default() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+
+        let now = "2024-01-01T00:00:00+00:00";
+        conn.execute(
+            "INSERT INTO ssh_keys (name, path, is_default, created_at, updated_at)
+             VALUES ('a', '/a', 1, ?1, ?1)",
+            rusqlite::params![now],
+        ).unwrap();
+
+        // Inserting a second default must not raise UNIQUE constraint failed,
+        // and the trigger must flip the first row back to non-default.
+        conn.execute(
+            "INSERT INTO ssh_keys (name, path, is_default, created_at, updated_at)
+             VALUES ('b', '/b', 1, ?1, ?1)",
+            rusqlite::params![now],
+        ).unwrap();
+
+        let defaults: i64 = conn
+            .query_row("SELECT COUNT(*) FROM ssh_keys WHERE is_default = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(defaults, 1);
+
+        // Flipping an existing row to default via UPDATE must behave the same way.
+        let first_id: i64 = conn
+            .query_row("SELECT id FROM ssh_keys WHERE name = 'a'", [], |row| row.get(0))
+            .unwrap();
+        conn.execute("UPDATE ssh_keys SET is_default = 1 WHERE id = ?1", rusqlite::params![first_id]).unwrap();
+
+        let defaults: i64 = conn
+            .query_row("SELECT COUNT(*) FROM ssh_keys WHERE is_default = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(defaults, 1);
+
+        let is_default: i64 = conn
+            .query_row("SELECT is_default FROM ssh_keys WHERE name = 'a'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(is_default, 1);
+    }
+
+    #[test]
+    fn upgrading_a_legacy_install_adds_the_servers_ssh_key_fk_and_keeps_data() {
+        // Simulates an install that predates schema versioning: chunk0-2
+        // added `servers.ssh_key_id` directly, with no FK, before V1 existed.
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE ssh_keys (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL,
+                is_default INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE servers (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                host TEXT NOT NULL,
+                port INTEGER NOT NULL DEFAULT 22,
+                username TEXT NOT NULL,
+                ssh_key_id INTEGER,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );",
+        ).unwrap();
+
+        let now = "2024-01-01T00:00:00+00:00";
+        conn.execute(
+            "INSERT INTO ssh_keys (id, name, path, created_at, updated_at) VALUES (1, 'a', '/a', ?1, ?1)",
+            rusqlite::params![now],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO servers (id, name, host, username, ssh_key_id, created_at, updated_at)
+             VALUES (42, 'box', 'example.com', 'root', 1, ?1, ?1)",
+            rusqlite::params![now],
+        ).unwrap();
+
+        // Mark V1 already applied, matching a real legacy install's recorded state.
+        conn.execute(
+            "CREATE TABLE schema_version (version INTEGER PRIMARY KEY, name TEXT NOT NULL, applied_at TEXT NOT NULL)",
+            [],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, name, applied_at) VALUES (1, 'initial', ?1)",
+            rusqlite::params![now],
+        ).unwrap();
+
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+        run_migrations(&conn).unwrap();
+
+        let fk_count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pragma_foreign_key_list('servers')", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(fk_count, 1, "servers.ssh_key_id must gain a foreign key to ssh_keys");
+
+        let (name, ssh_key_id): (String, i64) = conn
+            .query_row("SELECT name, ssh_key_id FROM servers WHERE id = 42", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((name, ssh_key_id), ("box".to_string(), 1));
+
+        // Deleting the referenced key must now cascade per ON DELETE SET NULL.
+        conn.execute("DELETE FROM ssh_keys WHERE id = 1", []).unwrap();
+        let ssh_key_id: Option<i64> = conn
+            .query_row("SELECT ssh_key_id FROM servers WHERE id = 42", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(ssh_key_id, None);
+
+        // The autoincrement counter must continue past the copied row, not restart at 1.
+        conn.execute(
+            "INSERT INTO servers (name, host, username, created_at, updated_at) VALUES ('new', 'h', 'u', ?1, ?1)",
+            rusqlite::params![now],
+        ).unwrap();
+        let new_id: i64 = conn.last_insert_rowid();
+        assert!(new_id > 42, "new row id {} should be greater than the pre-existing row's id 42", new_id);
+    }
+}
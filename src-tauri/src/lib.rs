@@ -1,9 +1,12 @@
 mod database;
 mod models;
 mod controllers;
+mod ssh;
 
 use tauri::AppHandle;
 use database::connection as conn;
+use ssh::SshPoolState;
+use ssh::agent::SshAgentState;
 
 #[tauri::command]
 fn init_app(app_handle: AppHandle) -> Result<String, String> {
@@ -24,6 +27,8 @@ fn init_app(app_handle: AppHandle) -> Result<String, String> {
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(SshPoolState::default())
+        .manage(SshAgentState::default())
         .invoke_handler(tauri::generate_handler![
             init_app,
 
@@ -37,6 +42,16 @@ pub fn run() {
             controllers::ssh_key::get_ssh_key,
             controllers::ssh_key::set_default_ssh_key,
             controllers::ssh_key::generate_ssh_key,
+            controllers::ssh_key::deploy_public_key,
+            controllers::ssh_key::renew_key,
+
+            controllers::ssh_session::connect_server,
+            controllers::ssh_session::run_command,
+            controllers::ssh_session::disconnect_server,
+
+            controllers::ssh_agent::start_agent,
+            controllers::ssh_agent::stop_agent,
+            controllers::ssh_agent::unlock_key,
 
             controllers::setting::get_setting,
             controllers::setting::get_settings,
@@ -0,0 +1,329 @@
+//! A minimal ssh-agent implementation that serves identities managed by
+//! Syndeos over a local socket, so external tools (`git`, `ssh`) can
+//! authenticate with keys that otherwise never touch disk unencrypted.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use interprocess::local_socket::{LocalSocketListener, LocalSocketStream, NameTypeSupport};
+use rsa::pkcs1v15::SigningKey;
+use rusqlite::params;
+use sha2::{Sha256, Sha512};
+use signature::{Signer, SignatureEncoding};
+use ssh_key::private::{KeypairData, RsaKeypair};
+use ssh_key::{Algorithm, HashAlg, PrivateKey, PublicKey, Signature};
+use tauri::AppHandle;
+use zeroize::Zeroize;
+
+use crate::database::connection::get as get_db;
+
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+const SSH_AGENT_FAILURE: u8 = 5;
+
+const SSH_AGENT_RSA_SHA2_256: u32 = 1 << 1;
+const SSH_AGENT_RSA_SHA2_512: u32 = 1 << 2;
+
+/// In-memory cache of passphrases the user has unlocked this session; never
+/// persisted, and kept only until the agent stops (or `unlock_key` overwrites
+/// it), so a key stays usable for the rest of the session after one unlock.
+type PassphraseCache = Arc<Mutex<HashMap<i64, String>>>;
+
+#[derive(Default)]
+pub struct SshAgentState {
+    running: Arc<AtomicBool>,
+    socket_path: Mutex<Option<String>>,
+    passphrases: PassphraseCache,
+}
+
+/// Picks the socket path to bind the agent on. Path-based sockets are placed
+/// inside a fresh, private (0700) directory so no other local user can reach
+/// the socket file to begin with; `start` additionally locks the socket file
+/// itself down to 0600 once it exists.
+fn default_socket_path() -> Result<String, String> {
+    match NameTypeSupport::query() {
+        NameTypeSupport::OnlyPaths | NameTypeSupport::Both => {
+            let dir = std::env::temp_dir().join(format!("syndeos-agent.{}", std::process::id()));
+            std::fs::create_dir(&dir).map_err(|e| format!("Failed to create agent socket dir: {}", e))?;
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))
+                    .map_err(|e| format!("Failed to harden agent socket dir: {}", e))?;
+            }
+
+            Ok(dir.join("agent.sock").to_string_lossy().into_owned())
+        }
+        NameTypeSupport::OnlyNamespaced => Ok("@syndeos-agent".to_string()),
+    }
+}
+
+/// Caches a passphrase in memory so the agent can decrypt `key_id` on demand.
+pub fn unlock_key(state: &SshAgentState, key_id: i64, passphrase: String) {
+    state.passphrases.lock().unwrap().insert(key_id, passphrase);
+}
+
+pub fn start(app_handle: &AppHandle, state: &SshAgentState) -> Result<String, String> {
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("SSH agent is already running".into());
+    }
+
+    let socket_path = default_socket_path()?;
+    let listener = LocalSocketListener::bind(socket_path.clone())
+        .map_err(|e| format!("Failed to bind agent socket: {}", e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if std::path::Path::new(&socket_path).exists() {
+            std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600))
+                .map_err(|e| format!("Failed to harden agent socket: {}", e))?;
+        }
+    }
+
+    let conn = get_db(app_handle)?;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES ('ssh_agent_socket', ?1)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![socket_path],
+    ).map_err(|e| e.to_string())?;
+
+    let running = state.running.clone();
+    let passphrases = state.passphrases.clone();
+    let app_handle = app_handle.clone();
+
+    thread::spawn(move || {
+        while running.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok(stream) => {
+                    let app_handle = app_handle.clone();
+                    let passphrases = passphrases.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream, &app_handle, &passphrases) {
+                            eprintln!("ssh-agent connection error: {}", e);
+                        }
+                    });
+                }
+                Err(e) => eprintln!("ssh-agent accept error: {}", e),
+            }
+        }
+    });
+
+    *state.socket_path.lock().unwrap() = Some(socket_path.clone());
+    Ok(socket_path)
+}
+
+pub fn stop(state: &SshAgentState) -> Result<(), String> {
+    state.running.store(false, Ordering::SeqCst);
+    state.passphrases.lock().unwrap().clear();
+    if let Some(path) = state.socket_path.lock().unwrap().take() {
+        let _ = std::fs::remove_file(&path);
+        if let Some(dir) = std::path::Path::new(&path).parent() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(mut stream: LocalSocketStream, app_handle: &AppHandle, passphrases: &PassphraseCache) -> Result<(), String> {
+    loop {
+        let mut len_buf = [0u8; 4];
+        if stream.read_exact(&mut len_buf).is_err() {
+            return Ok(()); // client disconnected
+        }
+        let len = u32::from_be_bytes(len_buf) as usize;
+
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body).map_err(|e| e.to_string())?;
+
+        let response = match body.first().copied() {
+            Some(SSH_AGENTC_REQUEST_IDENTITIES) => list_identities(app_handle),
+            Some(SSH_AGENTC_SIGN_REQUEST) => sign_request(app_handle, passphrases, &body[1..]),
+            _ => Ok(vec![SSH_AGENT_FAILURE]),
+        }.unwrap_or_else(|_| vec![SSH_AGENT_FAILURE]);
+
+        stream.write_all(&(response.len() as u32).to_be_bytes()).map_err(|e| e.to_string())?;
+        stream.write_all(&response).map_err(|e| e.to_string())?;
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn read_string(buf: &[u8], pos: &mut usize) -> Result<Vec<u8>, String> {
+    if *pos + 4 > buf.len() {
+        return Err("truncated ssh-agent message".into());
+    }
+    let len = u32::from_be_bytes(buf[*pos..*pos + 4].try_into().unwrap()) as usize;
+    *pos += 4;
+    if *pos + len > buf.len() {
+        return Err("truncated ssh-agent message".into());
+    }
+    let value = buf[*pos..*pos + len].to_vec();
+    *pos += len;
+    Ok(value)
+}
+
+fn list_identities(app_handle: &AppHandle) -> Result<Vec<u8>, String> {
+    let conn = get_db(app_handle)?;
+    let mut stmt = conn.prepare("SELECT name, path FROM ssh_keys").map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (name, path) = row.map_err(|e| e.to_string())?;
+        let public = match std::fs::read_to_string(format!("{}.pub", path)) {
+            Ok(contents) => contents,
+            Err(_) => continue, // key file missing/unreadable; skip rather than fail the whole list
+        };
+        if let Ok(public_key) = public.parse::<PublicKey>() {
+            entries.push((public_key, name));
+        }
+    }
+
+    let mut out = vec![SSH_AGENT_IDENTITIES_ANSWER];
+    out.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for (public_key, name) in entries {
+        let blob = public_key.to_bytes().map_err(|e| e.to_string())?;
+        write_string(&mut out, &blob);
+        write_string(&mut out, name.as_bytes());
+    }
+    Ok(out)
+}
+
+fn sign_request(app_handle: &AppHandle, passphrases: &PassphraseCache, body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut pos = 0;
+    let key_blob = read_string(body, &mut pos)?;
+    let data = read_string(body, &mut pos)?;
+    let flags = if body.len() >= pos + 4 {
+        u32::from_be_bytes(body[pos..pos + 4].try_into().unwrap())
+    } else {
+        0
+    };
+
+    let conn = get_db(app_handle)?;
+    let mut stmt = conn.prepare("SELECT id, path FROM ssh_keys").map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    for row in rows {
+        let (key_id, path) = row.map_err(|e| e.to_string())?;
+        let Ok(public) = std::fs::read_to_string(format!("{}.pub", path)) else { continue };
+        let Ok(public_key) = public.parse::<PublicKey>() else { continue };
+        let Ok(blob) = public_key.to_bytes() else { continue };
+        if blob != key_blob {
+            continue;
+        }
+
+        let mut passphrase = passphrases.lock().unwrap().get(&key_id).cloned();
+        let private_key_pem = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        let mut private_key: PrivateKey = private_key_pem.parse().map_err(|e: ssh_key::Error| e.to_string())?;
+        if private_key.is_encrypted() {
+            let passphrase = passphrase.as_deref().ok_or("key is locked; no cached passphrase")?;
+            private_key = private_key.decrypt(passphrase).map_err(|e| e.to_string())?;
+        }
+        if let Some(ref mut p) = passphrase {
+            p.zeroize();
+        }
+
+        let rsa_hash_hint = match flags {
+            f if f & SSH_AGENT_RSA_SHA2_512 != 0 => Some(HashAlg::Sha512),
+            f if f & SSH_AGENT_RSA_SHA2_256 != 0 => Some(HashAlg::Sha256),
+            _ => None,
+        };
+
+        // `PrivateKey`'s blanket `Signer` impl always signs RSA keys with its
+        // own default hash, so a client that explicitly asked for
+        // rsa-sha2-256/512 via the SIGN_REQUEST flags can silently get back a
+        // signature tagged with a different algorithm name. Sign RSA keys
+        // ourselves against the requested hash so the response always
+        // matches what was asked for.
+        let signature = match (private_key.key_data(), rsa_hash_hint) {
+            (KeypairData::Rsa(rsa_keypair), Some(hash_alg)) => sign_rsa(rsa_keypair, hash_alg, &data)?,
+            _ => private_key.try_sign(&data).map_err(|e| e.to_string())?,
+        };
+        let encoded = ssh_key::Signature::to_bytes(&signature).map_err(|e| e.to_string())?;
+
+        let mut out = vec![SSH_AGENT_SIGN_RESPONSE];
+        write_string(&mut out, &encoded);
+        return Ok(out);
+    }
+
+    Ok(vec![SSH_AGENT_FAILURE])
+}
+
+/// Signs `data` with `keypair` using exactly the requested RSA hash
+/// algorithm, producing a signature tagged `rsa-sha2-256` or `rsa-sha2-512`
+/// to match what the client asked for.
+fn sign_rsa(keypair: &RsaKeypair, hash_alg: HashAlg, data: &[u8]) -> Result<Signature, String> {
+    let private_key = rsa::RsaPrivateKey::try_from(keypair).map_err(|e| e.to_string())?;
+    let bytes: Vec<u8> = match hash_alg {
+        HashAlg::Sha256 => SigningKey::<Sha256>::new(private_key)
+            .try_sign(data)
+            .map_err(|e| e.to_string())?
+            .to_vec(),
+        HashAlg::Sha512 => SigningKey::<Sha512>::new(private_key)
+            .try_sign(data)
+            .map_err(|e| e.to_string())?
+            .to_vec(),
+        other => return Err(format!("unsupported RSA hash algorithm: {:?}", other)),
+    };
+
+    Signature::new(Algorithm::Rsa { hash: Some(hash_alg) }, bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_then_read_string_round_trips() {
+        let mut buf = Vec::new();
+        write_string(&mut buf, b"hello");
+        write_string(&mut buf, b"");
+
+        let mut pos = 0;
+        assert_eq!(read_string(&buf, &mut pos).unwrap(), b"hello");
+        assert_eq!(read_string(&buf, &mut pos).unwrap(), b"");
+        assert_eq!(pos, buf.len());
+    }
+
+    #[test]
+    fn read_string_rejects_truncated_length_prefix() {
+        let buf = [0u8, 0, 0];
+        let mut pos = 0;
+        assert!(read_string(&buf, &mut pos).is_err());
+    }
+
+    #[test]
+    fn read_string_rejects_length_past_buffer_end() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&10u32.to_be_bytes());
+        buf.extend_from_slice(b"short");
+
+        let mut pos = 0;
+        assert!(read_string(&buf, &mut pos).is_err());
+    }
+
+    #[test]
+    fn sign_rsa_honors_the_requested_hash_algorithm() {
+        let keypair = RsaKeypair::random(&mut ssh_key::rand_core::OsRng, 2048).unwrap();
+
+        let sha256_sig = sign_rsa(&keypair, HashAlg::Sha256, b"some data").unwrap();
+        assert_eq!(sha256_sig.algorithm(), Algorithm::Rsa { hash: Some(HashAlg::Sha256) });
+
+        let sha512_sig = sign_rsa(&keypair, HashAlg::Sha512, b"some data").unwrap();
+        assert_eq!(sha512_sig.algorithm(), Algorithm::Rsa { hash: Some(HashAlg::Sha512) });
+    }
+}
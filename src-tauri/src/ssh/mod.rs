@@ -0,0 +1,261 @@
+pub mod agent;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use libssh_rs::{AuthStatus, PrivateKey as LibsshPrivateKey, Session, SshOption};
+use r2d2::{ManageConnection, Pool};
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, Manager};
+
+use crate::database::connection::get as get_db;
+
+/// Result of a command executed over a pooled SSH session.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+}
+
+#[derive(Debug)]
+pub enum SshError {
+    ServerNotFound(i64),
+    KeyNotFound(i64),
+    KeyRead(String),
+    AuthFailed(String),
+    Ssh(String),
+}
+
+impl fmt::Display for SshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SshError::ServerNotFound(id) => write!(f, "server {} not found", id),
+            SshError::KeyNotFound(id) => write!(f, "ssh key {} not found", id),
+            SshError::KeyRead(msg) => write!(f, "failed to read private key: {}", msg),
+            SshError::AuthFailed(msg) => write!(f, "authentication failed: {}", msg),
+            SshError::Ssh(msg) => write!(f, "ssh error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SshError {}
+
+impl From<SshError> for String {
+    fn from(err: SshError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Everything needed to (re)establish an authenticated session to one server.
+struct SshConnectionManager {
+    host: String,
+    port: u16,
+    username: String,
+    private_key: LibsshPrivateKey,
+}
+
+impl ManageConnection for SshConnectionManager {
+    type Connection = Session;
+    type Error = SshError;
+
+    fn connect(&self) -> Result<Session, SshError> {
+        let session = Session::new().map_err(|e| SshError::Ssh(e.to_string()))?;
+        session
+            .set_option(SshOption::Hostname(self.host.clone()))
+            .map_err(|e| SshError::Ssh(e.to_string()))?;
+        session
+            .set_option(SshOption::Port(self.port))
+            .map_err(|e| SshError::Ssh(e.to_string()))?;
+        session
+            .set_option(SshOption::User(Some(self.username.clone())))
+            .map_err(|e| SshError::Ssh(e.to_string()))?;
+        session.connect().map_err(|e| SshError::Ssh(e.to_string()))?;
+
+        match session
+            .userauth_public_key(&self.username, &self.private_key)
+            .map_err(|e| SshError::Ssh(e.to_string()))?
+        {
+            AuthStatus::Success => Ok(session),
+            other => Err(SshError::AuthFailed(format!("{:?}", other))),
+        }
+    }
+
+    fn is_valid(&self, conn: &mut Session) -> Result<(), SshError> {
+        if conn.is_connected() {
+            Ok(())
+        } else {
+            Err(SshError::Ssh("session is no longer connected".into()))
+        }
+    }
+
+    fn has_broken(&self, conn: &mut Session) -> bool {
+        !conn.is_connected()
+    }
+}
+
+/// Tauri-managed state holding one connection pool per stored server id.
+#[derive(Default)]
+pub struct SshPoolState(Mutex<HashMap<i64, Pool<SshConnectionManager>>>);
+
+/// Reads the configured SSH directory from settings, falling back to `~/.ssh`.
+pub fn ssh_dir(conn: &Connection) -> Result<PathBuf, String> {
+    let configured: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = 'ssh_dir'",
+            [],
+            |row| row.get(0),
+        )
+        .ok();
+
+    match configured {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => dirs::home_dir()
+            .map(|home| home.join(".ssh"))
+            .ok_or_else(|| "Could not get home directory".to_string()),
+    }
+}
+
+fn load_private_key(conn: &Connection, key_id: i64, passphrase: Option<&str>) -> Result<LibsshPrivateKey, SshError> {
+    let path: String = conn
+        .query_row(
+            "SELECT path FROM ssh_keys WHERE id = ?1",
+            params![key_id],
+            |row| row.get(0),
+        )
+        .map_err(|_| SshError::KeyNotFound(key_id))?;
+
+    let contents = fs::read_to_string(&path).map_err(|e| SshError::KeyRead(e.to_string()))?;
+    LibsshPrivateKey::from_base64(&contents, passphrase)
+        .map_err(|e| SshError::KeyRead(e.to_string()))
+}
+
+/// Looks up (or lazily creates) the connection pool for `server_id`.
+fn get_or_create_pool(app_handle: &AppHandle, server_id: i64, passphrase: Option<String>) -> Result<Pool<SshConnectionManager>, SshError> {
+    let state = app_handle.state::<SshPoolState>();
+    {
+        let pools = state.0.lock().map_err(|e| SshError::Ssh(e.to_string()))?;
+        if let Some(pool) = pools.get(&server_id) {
+            return Ok(pool.clone());
+        }
+    }
+
+    let conn = get_db(app_handle).map_err(SshError::Ssh)?;
+    let (host, port, username, ssh_key_id): (String, u16, String, i64) = conn
+        .query_row(
+            "SELECT host, port, username, ssh_key_id FROM servers WHERE id = ?1",
+            params![server_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|_| SshError::ServerNotFound(server_id))?;
+
+    let private_key = load_private_key(&conn, ssh_key_id, passphrase.as_deref())?;
+
+    let manager = SshConnectionManager {
+        host,
+        port,
+        username,
+        private_key,
+    };
+    let pool = Pool::builder()
+        .max_size(4)
+        .build(manager)
+        .map_err(|e| SshError::Ssh(e.to_string()))?;
+
+    let mut pools = state.0.lock().map_err(|e| SshError::Ssh(e.to_string()))?;
+    pools.insert(server_id, pool.clone());
+    Ok(pool)
+}
+
+pub fn connect(app_handle: &AppHandle, server_id: i64, passphrase: Option<String>) -> Result<(), String> {
+    get_or_create_pool(app_handle, server_id, passphrase)?;
+    Ok(())
+}
+
+pub fn disconnect(app_handle: &AppHandle, server_id: i64) -> Result<(), String> {
+    let state = app_handle.state::<SshPoolState>();
+    let mut pools = state.0.lock().map_err(|e| e.to_string())?;
+    pools.remove(&server_id);
+    Ok(())
+}
+
+pub fn run(app_handle: &AppHandle, server_id: i64, command: String) -> Result<CommandOutput, String> {
+    let pool = get_or_create_pool(app_handle, server_id, None)?;
+    let session = pool.get().map_err(|e| e.to_string())?;
+    run_on_session(&session, &command)
+}
+
+/// Runs `command` over a one-off, unpooled session authenticated with a
+/// password rather than a stored key. Used to bootstrap trust on a host that
+/// doesn't already have one of our keys in its `authorized_keys` yet.
+pub fn run_with_password(
+    host: &str,
+    port: u16,
+    username: &str,
+    password: &str,
+    command: &str,
+) -> Result<CommandOutput, String> {
+    let session = Session::new().map_err(|e| e.to_string())?;
+    session
+        .set_option(SshOption::Hostname(host.to_string()))
+        .map_err(|e| e.to_string())?;
+    session
+        .set_option(SshOption::Port(port))
+        .map_err(|e| e.to_string())?;
+    session
+        .set_option(SshOption::User(Some(username.to_string())))
+        .map_err(|e| e.to_string())?;
+    session.connect().map_err(|e| e.to_string())?;
+
+    match session
+        .userauth_password(Some(username), Some(password))
+        .map_err(|e| e.to_string())?
+    {
+        AuthStatus::Success => {}
+        other => return Err(format!("authentication failed: {:?}", other)),
+    }
+
+    run_on_session(&session, command)
+}
+
+fn run_on_session(session: &Session, command: &str) -> Result<CommandOutput, String> {
+    let channel = session.new_channel().map_err(|e| e.to_string())?;
+    channel.open_session().map_err(|e| e.to_string())?;
+    channel.request_exec(command).map_err(|e| e.to_string())?;
+
+    // Read stdout and stderr interleaved rather than draining one to
+    // completion before touching the other: a remote command that fills the
+    // stderr window while we sit blocked reading stdout (or vice versa)
+    // would otherwise deadlock the session.
+    let mut stdout_bytes = Vec::new();
+    let mut stderr_bytes = Vec::new();
+    let read_timeout = Duration::from_millis(100);
+    let mut buf = [0u8; 4096];
+    loop {
+        let read_stdout = channel
+            .read_timeout(&mut buf, false, Some(read_timeout))
+            .map_err(|e| e.to_string())?;
+        stdout_bytes.extend_from_slice(&buf[..read_stdout]);
+
+        let read_stderr = channel
+            .read_timeout(&mut buf, true, Some(read_timeout))
+            .map_err(|e| e.to_string())?;
+        stderr_bytes.extend_from_slice(&buf[..read_stderr]);
+
+        if read_stdout == 0 && read_stderr == 0 && channel.is_eof() {
+            break;
+        }
+    }
+    let stdout = String::from_utf8_lossy(&stdout_bytes).into_owned();
+    let stderr = String::from_utf8_lossy(&stderr_bytes).into_owned();
+
+    let exit_code = channel.get_exit_status().unwrap_or(-1);
+    let _ = channel.send_eof();
+    let _ = channel.close();
+
+    Ok(CommandOutput { stdout, stderr, exit_code })
+}